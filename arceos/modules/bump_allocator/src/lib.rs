@@ -5,70 +5,223 @@ use core::ptr::NonNull;
 use allocator::{BaseAllocator, ByteAllocator, PageAllocator};
 
 use allocator::AllocError;
-/// Early memory allocator
-/// Use it before formal bytes-allocator and pages-allocator can work!
-/// This is a double-end memory range:
-/// - Alloc bytes forward
-/// - Alloc pages backward
-///
-/// [ bytes-used | avail-area | pages-used ]
-/// |            | -->    <-- |            |
-/// start       b_pos        p_pos       end
-///
-/// For bytes area, 'count' records number of allocations.
-/// When it goes down to ZERO, free bytes-used area.
-/// For pages area, it will never be freed!
-///
-pub struct EarlyAllocator<const PAGE_SIZE: usize> {
+
+/// 每个 bitmap 块（chunk）覆盖的页数，等于一个 `u64` 的位数。
+const CHUNK_BITS: usize = 64;
+
+/// 一个 bitmap 块的游程摘要：`start`/`end` 分别是块开头、结尾连续空闲页的
+/// 长度（可以和相邻块的游程相连），`max` 是块内部最长的连续空闲游程。
+/// 搜索空闲页时优先靠这三个数字判断，而不必逐位扫描整块。
+#[derive(Clone, Copy)]
+struct ChunkSummary {
+    start: u8,
+    max: u8,
+    end: u8,
+}
+
+impl ChunkSummary {
+    const fn empty() -> Self {
+        Self {
+            start: 0,
+            max: 0,
+            end: 0,
+        }
+    }
+
+    /// 由一个 bitmap 字重新计算摘要，1 表示空闲、0 表示已用。
+    fn from_word(word: u64) -> Self {
+        if word == 0 {
+            return Self::empty();
+        }
+        if word == u64::MAX {
+            return Self {
+                start: CHUNK_BITS as u8,
+                max: CHUNK_BITS as u8,
+                end: CHUNK_BITS as u8,
+            };
+        }
+        let start = word.trailing_ones() as u8;
+        let end = word.leading_ones() as u8;
+        let mut max = 0u8;
+        let mut run = 0u8;
+        for bit in 0..CHUNK_BITS {
+            if word & (1 << bit) != 0 {
+                run += 1;
+                max = max.max(run);
+            } else {
+                run = 0;
+            }
+        }
+        Self { start, max, end }
+    }
+}
+
+/// 一段独立的可用内存区间，拥有自己的双端布局：
+/// `[ bytes-used | avail-area | pages-used ]`，字节从 `b_pos` 向前分配；
+/// 页从 free-page bitmap 里地址最低的合适空闲游程中分配、可以回收，
+/// `p_pos` 记录分出过的最低页地址，回收页时不会回退。
+/// `EarlyAllocator` 持有一个定长的 `Region` 数组来支持多段不连续内存。
+#[derive(Clone, Copy)]
+struct Region<const PAGE_SIZE: usize, const MAX_CHUNKS: usize> {
     start: usize,
     end: usize,
     b_pos: usize,
     p_pos: usize,
     count: usize,
+    /// 页 bitmap：每一位对应一个页，1 表示空闲，0 表示已用
+    bitmap: [u64; MAX_CHUNKS],
+    /// 与 `bitmap` 一一对应的游程摘要缓存
+    summaries: [ChunkSummary; MAX_CHUNKS],
+    /// 字节区域已经占用、因而永久保留（不会再分给页分配器）的页数
+    reserved_pages: usize,
 }
 
-impl<const PAGE_SIZE: usize> EarlyAllocator<PAGE_SIZE> {
-    pub const fn new() -> Self {
+impl<const PAGE_SIZE: usize, const MAX_CHUNKS: usize> Region<PAGE_SIZE, MAX_CHUNKS> {
+    const fn empty() -> Self {
         Self {
             start: 0,
             end: 0,
-            b_pos : 0,
+            b_pos: 0,
             p_pos: 0,
-            count: 0
+            count: 0,
+            bitmap: [0; MAX_CHUNKS],
+            summaries: [ChunkSummary::empty(); MAX_CHUNKS],
+            reserved_pages: 0,
         }
     }
-}
 
-impl<const PAGE_SIZE: usize> BaseAllocator for EarlyAllocator<PAGE_SIZE> {
     fn init(&mut self, start: usize, size: usize) {
         self.start = start;
         self.end = start + size;
         self.b_pos = self.start;
         self.p_pos = self.end;
+        self.reserved_pages = 0;
+
+        let total_pages =
+            core::cmp::min((self.end - self.start) / PAGE_SIZE, MAX_CHUNKS * CHUNK_BITS);
+        for chunk in 0..MAX_CHUNKS {
+            let chunk_base = chunk * CHUNK_BITS;
+            self.bitmap[chunk] = if chunk_base >= total_pages {
+                0
+            } else if chunk_base + CHUNK_BITS <= total_pages {
+                u64::MAX
+            } else {
+                (1u64 << (total_pages - chunk_base)) - 1
+            };
+            self.summaries[chunk] = ChunkSummary::from_word(self.bitmap[chunk]);
+        }
     }
 
-    fn add_memory(&mut self, start: usize, size: usize) -> allocator::AllocResult {
-        todo!()
+    fn contains(&self, addr: usize) -> bool {
+        addr >= self.start && addr < self.end
     }
-}
 
-impl<const PAGE_SIZE: usize> ByteAllocator for EarlyAllocator<PAGE_SIZE> {
-    fn alloc(
-        &mut self,
-        layout: core::alloc::Layout,
-    ) -> allocator::AllocResult<core::ptr::NonNull<u8>> {
-        if self.available_bytes() < layout.size(){
-            return Err(AllocError::NoMemory);
+    fn page_index(&self, addr: usize) -> usize {
+        (addr - self.start) / PAGE_SIZE
+    }
+
+    fn page_addr(&self, index: usize) -> usize {
+        self.start + index * PAGE_SIZE
+    }
+
+    fn set_page_free(&mut self, index: usize, free: bool) {
+        let chunk = index / CHUNK_BITS;
+        let bit = index % CHUNK_BITS;
+        if free {
+            self.bitmap[chunk] |= 1 << bit;
+        } else {
+            self.bitmap[chunk] &= !(1 << bit);
+        }
+        self.summaries[chunk] = ChunkSummary::from_word(self.bitmap[chunk]);
+    }
+
+    /// 字节区域每次向前推进后调用：把新纳入字节区域 `[start, b_pos)`
+    /// 的页在 bitmap 中标记为已用，这些页永久不再分给页分配器。
+    ///
+    /// `b_pos` 可能推进到 bitmap 能追踪的范围（`MAX_CHUNKS * CHUNK_BITS`
+    /// 页）之外——`init` 本就把超出这个范围的页视为从未存在于页分配器里，
+    /// 所以这里把边界也钳在同一处，而不是对不存在的 chunk 继续标记。
+    fn reserve_pages_for_bytes(&mut self) {
+        let boundary = core::cmp::min(
+            (self.b_pos - self.start + PAGE_SIZE - 1) / PAGE_SIZE,
+            MAX_CHUNKS * CHUNK_BITS,
+        );
+        while self.reserved_pages < boundary {
+            self.set_page_free(self.reserved_pages, false);
+            self.reserved_pages += 1;
+        }
+    }
+
+    /// 在某一块内部定位一段长度至少为 `num_pages` 的连续空闲游程，
+    /// 返回该块内的起始位偏移。
+    fn locate_run_in_word(word: u64, num_pages: usize) -> Option<usize> {
+        let mut run = 0usize;
+        for bit in 0..CHUNK_BITS {
+            if word & (1 << bit) != 0 {
+                run += 1;
+                if run >= num_pages {
+                    return Some(bit + 1 - num_pages);
+                }
+            } else {
+                run = 0;
+            }
+        }
+        None
+    }
+
+    /// 在整个 bitmap 中搜索一段长度至少为 `num_pages` 的连续空闲页，
+    /// 返回其起始页下标。
+    ///
+    /// 先从左到右扫描摘要，把每块开头的 `start` 游程累加到上一块结尾的
+    /// 游程上；一旦累加值达到 `num_pages` 即找到一段跨块相连的游程。若某块
+    /// 并未整块空闲（`end != CHUNK_BITS`），说明游程在此处断开，改从该块
+    /// 结尾的 `end` 游程重新累加。这条路径找不到时，再单独检查每块内部的
+    /// 最长游程 `max`（游程完全落在一块之内、不与相邻块相连的情况）。
+    fn find_free_run(&self, num_pages: usize) -> Option<usize> {
+        if num_pages == 0 || num_pages > MAX_CHUNKS * CHUNK_BITS {
+            return None;
+        }
+
+        let mut acc = 0usize;
+        let mut run_start = 0usize;
+        for (chunk, summary) in self.summaries.iter().enumerate() {
+            let chunk_base = chunk * CHUNK_BITS;
+            acc += summary.start as usize;
+            if acc >= num_pages {
+                return Some(run_start);
+            }
+            if summary.end as usize != CHUNK_BITS {
+                acc = summary.end as usize;
+                run_start = chunk_base + CHUNK_BITS - summary.end as usize;
+            }
+        }
+
+        if num_pages <= CHUNK_BITS {
+            for (chunk, summary) in self.summaries.iter().enumerate() {
+                if summary.max as usize >= num_pages {
+                    let offset = Self::locate_run_in_word(self.bitmap[chunk], num_pages)?;
+                    return Some(chunk * CHUNK_BITS + offset);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn alloc_bytes(&mut self, layout: core::alloc::Layout) -> Option<NonNull<u8>> {
+        if self.available_bytes() < layout.size() {
+            return None;
         }
         let start = self.b_pos;
         self.b_pos += layout.pad_to_align().size();
-        self.count+=1;
-        Ok(NonNull::new(start as *mut u8).unwrap())
+        self.count += 1;
+        self.reserve_pages_for_bytes();
+        NonNull::new(start as *mut u8)
     }
 
-    fn dealloc(&mut self, pos: core::ptr::NonNull<u8>, layout: core::alloc::Layout) {
+    fn dealloc_bytes(&mut self) {
         self.count -= 1;
-        if self.count == 0{
+        if self.count == 0 {
             self.b_pos = self.start;
         }
     }
@@ -84,9 +237,165 @@ impl<const PAGE_SIZE: usize> ByteAllocator for EarlyAllocator<PAGE_SIZE> {
     fn available_bytes(&self) -> usize {
         self.p_pos - self.b_pos
     }
+
+    fn alloc_pages(&mut self, num_pages: usize, align_pow2: usize) -> Option<usize> {
+        // 需要额外的页数来保证在候选游程内部总能找到一个按 align_pow2
+        // 对齐的起始地址
+        let align_pages = align_pow2 / PAGE_SIZE;
+        let search_len = num_pages + align_pages - 1;
+        let run_start = self.find_free_run(search_len)?;
+
+        let raw_addr = self.page_addr(run_start);
+        let aligned_addr = (raw_addr + align_pow2 - 1) & !(align_pow2 - 1);
+        let start_index = self.page_index(aligned_addr);
+
+        for index in start_index..start_index + num_pages {
+            self.set_page_free(index, false);
+        }
+        if aligned_addr < self.p_pos {
+            self.p_pos = aligned_addr;
+        }
+
+        Some(aligned_addr)
+    }
+
+    fn dealloc_pages(&mut self, pos: usize, num_pages: usize) {
+        let start_index = self.page_index(pos);
+        for index in start_index..start_index + num_pages {
+            self.set_page_free(index, true);
+        }
+    }
+
+    fn total_pages(&self) -> usize {
+        (self.end - self.b_pos) / PAGE_SIZE
+    }
+
+    fn used_pages(&self) -> usize {
+        self.total_pages() - self.available_pages()
+    }
+
+    fn available_pages(&self) -> usize {
+        self.bitmap
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+/// Early memory allocator
+/// Use it before formal bytes-allocator and pages-allocator can work!
+/// Owns a small fixed-capacity list of [`Region`]s so it can manage several
+/// disjoint memory ranges (as real boot-time memory maps typically report),
+/// not just the single range passed to `init`.
+///
+/// Each region keeps bytes in front of pages:
+/// - Alloc bytes forward from `b_pos`, up to `p_pos`
+/// - Alloc pages from the lowest-address free run in the region's page
+///   bitmap; `p_pos` is lowered to the lowest page address handed out and
+///   is not raised again when pages are freed
+///
+/// [ bytes-used | avail-area | pages-used ]
+/// |            | -->        |            |
+/// start       b_pos        p_pos       end
+///
+/// `alloc`/`alloc_pages` try each region in turn, so a byte allocation
+/// spills into the next region once the current one runs out of forward
+/// space, and a page allocation moves on to the next region when the
+/// current region's bitmap has no free run that fits.
+/// `MAX_CHUNKS` bounds how many `u64` bitmap words (i.e. how many
+/// `CHUNK_BITS`-page chunks) a single region can track for page allocation;
+/// `MAX_REGIONS` bounds how many regions `init`/`add_memory` can register in
+/// total. Byte allocation is unaffected by `MAX_CHUNKS` — only pages past
+/// `MAX_CHUNKS * CHUNK_BITS` per region are excluded from the page
+/// allocator. Both default to modest values (32 chunks, i.e. 2048 pages per
+/// region, and 4 regions) so existing `EarlyAllocator<PAGE_SIZE>` call sites
+/// keep compiling unchanged; callers that need to page-allocate across a
+/// larger region should pass `MAX_CHUNKS` explicitly.
+pub struct EarlyAllocator<
+    const PAGE_SIZE: usize,
+    const MAX_CHUNKS: usize = 32,
+    const MAX_REGIONS: usize = 4,
+> {
+    regions: [Region<PAGE_SIZE, MAX_CHUNKS>; MAX_REGIONS],
+    region_count: usize,
+}
+
+impl<const PAGE_SIZE: usize, const MAX_CHUNKS: usize, const MAX_REGIONS: usize>
+    EarlyAllocator<PAGE_SIZE, MAX_CHUNKS, MAX_REGIONS>
+{
+    pub const fn new() -> Self {
+        Self {
+            regions: [Region::empty(); MAX_REGIONS],
+            region_count: 0,
+        }
+    }
+
+    fn regions(&self) -> &[Region<PAGE_SIZE, MAX_CHUNKS>] {
+        &self.regions[..self.region_count]
+    }
+
+    fn regions_mut(&mut self) -> &mut [Region<PAGE_SIZE, MAX_CHUNKS>] {
+        &mut self.regions[..self.region_count]
+    }
+}
+
+impl<const PAGE_SIZE: usize, const MAX_CHUNKS: usize, const MAX_REGIONS: usize> BaseAllocator
+    for EarlyAllocator<PAGE_SIZE, MAX_CHUNKS, MAX_REGIONS>
+{
+    fn init(&mut self, start: usize, size: usize) {
+        self.region_count = 0;
+        // region_count 刚被清零，MAX_REGIONS 至少为 1 时这里一定成功
+        let _ = self.add_memory(start, size);
+    }
+
+    fn add_memory(&mut self, start: usize, size: usize) -> allocator::AllocResult {
+        if self.region_count == MAX_REGIONS {
+            return Err(AllocError::NoMemory);
+        }
+        self.regions[self.region_count].init(start, size);
+        self.region_count += 1;
+        Ok(())
+    }
+}
+
+impl<const PAGE_SIZE: usize, const MAX_CHUNKS: usize, const MAX_REGIONS: usize> ByteAllocator
+    for EarlyAllocator<PAGE_SIZE, MAX_CHUNKS, MAX_REGIONS>
+{
+    fn alloc(
+        &mut self,
+        layout: core::alloc::Layout,
+    ) -> allocator::AllocResult<core::ptr::NonNull<u8>> {
+        for region in self.regions_mut() {
+            if let Some(ptr) = region.alloc_bytes(layout) {
+                return Ok(ptr);
+            }
+        }
+        Err(AllocError::NoMemory)
+    }
+
+    fn dealloc(&mut self, pos: core::ptr::NonNull<u8>, _layout: core::alloc::Layout) {
+        let addr = pos.as_ptr() as usize;
+        if let Some(region) = self.regions_mut().iter_mut().find(|r| r.contains(addr)) {
+            region.dealloc_bytes();
+        }
+    }
+
+    fn total_bytes(&self) -> usize {
+        self.regions().iter().map(Region::total_bytes).sum()
+    }
+
+    fn used_bytes(&self) -> usize {
+        self.regions().iter().map(Region::used_bytes).sum()
+    }
+
+    fn available_bytes(&self) -> usize {
+        self.regions().iter().map(Region::available_bytes).sum()
+    }
 }
 
-impl<const PAGE_SIZE: usize> PageAllocator for EarlyAllocator<PAGE_SIZE> {
+impl<const PAGE_SIZE: usize, const MAX_CHUNKS: usize, const MAX_REGIONS: usize> PageAllocator
+    for EarlyAllocator<PAGE_SIZE, MAX_CHUNKS, MAX_REGIONS>
+{
     const PAGE_SIZE: usize = PAGE_SIZE;
 
     fn alloc_pages(
@@ -94,37 +403,98 @@ impl<const PAGE_SIZE: usize> PageAllocator for EarlyAllocator<PAGE_SIZE> {
         num_pages: usize,
         align_pow2: usize,
     ) -> allocator::AllocResult<usize> {
-        if !align_pow2.is_power_of_two() || align_pow2 < PAGE_SIZE{
+        if !align_pow2.is_power_of_two() || align_pow2 < PAGE_SIZE {
             return Err(AllocError::InvalidParam);
         }
 
-        // 计算起始地址
-        let alloc_size = num_pages * PAGE_SIZE;
-        let mut start = self.p_pos - alloc_size;
-        start = start & !(align_pow2 - 1);
-
-        // 计算空间是否充足
-        if (self.p_pos - start) / PAGE_SIZE < self.available_pages(){
-            return Err(AllocError::NoMemory);
+        for region in self.regions_mut() {
+            if let Some(addr) = region.alloc_pages(num_pages, align_pow2) {
+                return Ok(addr);
+            }
         }
-
-        self.p_pos = start;
-        start
+        Err(AllocError::NoMemory)
     }
 
     fn dealloc_pages(&mut self, pos: usize, num_pages: usize) {
-        todo!()
+        if let Some(region) = self.regions_mut().iter_mut().find(|r| r.contains(pos)) {
+            region.dealloc_pages(pos, num_pages);
+        }
     }
 
     fn total_pages(&self) -> usize {
-        (self.end - self.b_pos) / PAGE_SIZE
+        self.regions().iter().map(Region::total_pages).sum()
     }
 
     fn used_pages(&self) -> usize {
-        (self.end - self.p_pos) / PAGE_SIZE
+        self.regions().iter().map(Region::used_pages).sum()
     }
 
     fn available_pages(&self) -> usize {
-        (self.p_pos - self.b_pos) / PAGE_SIZE
+        self.regions().iter().map(Region::available_pages).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE_SIZE: usize = 4096;
+
+    /// 回归测试：字节分配把 `b_pos` 推过 bitmap 能追踪的
+    /// `MAX_CHUNKS * CHUNK_BITS` 页边界时不应 panic——这些页本就在
+    /// `init` 时被排除在页分配器之外，`reserve_pages_for_bytes` 只需要
+    /// 把边界内的部分标记为已用。
+    #[test]
+    fn alloc_bytes_past_bitmap_boundary_does_not_panic() {
+        let mut alloc = EarlyAllocator::<PAGE_SIZE, 2, 1>::new();
+        alloc.init(0x1000, 1024 * PAGE_SIZE);
+
+        let layout = core::alloc::Layout::from_size_align(200 * PAGE_SIZE, 8).unwrap();
+        let ptr = match alloc.alloc(layout) {
+            Ok(ptr) => ptr,
+            Err(_) => panic!("region has plenty of byte space"),
+        };
+        assert_eq!(ptr.as_ptr() as usize, 0x1000);
+    }
+
+    /// 页分配/回收应当在 bitmap 里正确往返：分配后可用页数下降，
+    /// 回收后恢复，且回收的页能被再次分配出来。
+    #[test]
+    fn page_alloc_and_dealloc_round_trip() {
+        let mut alloc = EarlyAllocator::<PAGE_SIZE, 2, 1>::new();
+        alloc.init(0x1000, 128 * PAGE_SIZE);
+
+        let before = alloc.available_pages();
+        let addr = match alloc.alloc_pages(4, PAGE_SIZE) {
+            Ok(addr) => addr,
+            Err(_) => panic!("region has plenty of free pages"),
+        };
+        assert_eq!(alloc.available_pages(), before - 4);
+
+        alloc.dealloc_pages(addr, 4);
+        assert_eq!(alloc.available_pages(), before);
+
+        // 回收的页应当可以再次被分配出来
+        assert!(alloc.alloc_pages(4, PAGE_SIZE).is_ok());
     }
-}
\ No newline at end of file
+
+    /// 字节分配耗尽当前 region 后应当溢出到下一个 region，而不是报 `NoMemory`。
+    #[test]
+    fn byte_alloc_spills_into_next_region() {
+        let mut alloc = EarlyAllocator::<PAGE_SIZE, 4, 2>::new();
+        alloc.init(0x1000, 2 * PAGE_SIZE);
+        if alloc.add_memory(0x1000 + 0x10_0000, 2 * PAGE_SIZE).is_err() {
+            panic!("second region should register fine");
+        }
+
+        let layout = core::alloc::Layout::from_size_align(PAGE_SIZE, 8).unwrap();
+        assert!(alloc.alloc(layout).is_ok());
+        assert!(alloc.alloc(layout).is_ok());
+        // 前两次已经耗尽第一个 region 的可用字节空间，第三次应当落到第二个 region
+        let third = match alloc.alloc(layout) {
+            Ok(ptr) => ptr,
+            Err(_) => panic!("should spill into the second region"),
+        };
+        assert_eq!(third.as_ptr() as usize, 0x1000 + 0x10_0000);
+    }
+}
@@ -0,0 +1,276 @@
+//! `DiagnosticHashMap`：面向内核调试场景的 [`HashMap`] 包装器。
+//!
+//! 只在 `debug-map` feature 打开时编译，正常构建不会带上这部分开销。
+#![cfg(feature = "debug-map")]
+
+use super::hashmap::HashMap;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+use core::hash::Hash;
+
+const CANARY_SEED: u64 = 0x1337_c0de_cafe_f00d;
+const CANARY_PRIME: u64 = 0x100000001b3;
+const JOURNAL_CAPACITY: usize = 32;
+
+/// 记录在日志里的一次操作类型
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Insert,
+    Remove,
+    Get,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct JournalEntry {
+    op: Op,
+    bucket: usize,
+}
+
+/// 最近若干次操作组成的环形日志，供 canary 被破坏时做事后排查
+struct Journal {
+    entries: [Option<JournalEntry>; JOURNAL_CAPACITY],
+    next: usize,
+}
+
+impl Journal {
+    const fn new() -> Self {
+        Self {
+            entries: [None; JOURNAL_CAPACITY],
+            next: 0,
+        }
+    }
+
+    fn record(&mut self, op: Op, bucket: usize) {
+        self.entries[self.next] = Some(JournalEntry { op, bucket });
+        self.next = (self.next + 1) % JOURNAL_CAPACITY;
+    }
+}
+
+impl fmt::Debug for Journal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 按从旧到新的顺序列出日志，方便直接对照操作发生的先后
+        let mut list = f.debug_list();
+        for offset in 0..JOURNAL_CAPACITY {
+            let index = (self.next + offset) % JOURNAL_CAPACITY;
+            if let Some(entry) = self.entries[index] {
+                list.entry(&entry);
+            }
+        }
+        list.finish()
+    }
+}
+
+/// canary 只由桶下标和桶长度推导，与 `K`/`V` 无关，因此拆成自由函数，
+/// 方便不满足 `K: Hash + Eq` 约束的地方（比如 `ReadOnlyGuard` 的 `Drop`）直接调用。
+///
+/// 这只是一个长度一致性检查，不是围在桶存储周围的哨兵字——它能发现
+/// 「桶的长度跟上次记录的不一样」，但发现不了「桶里某个已有条目的内容
+/// 被原地改写、长度没变」这种破坏。
+fn canary_for(index: usize, len: usize) -> u64 {
+    CANARY_SEED ^ (index as u64).wrapping_mul(CANARY_PRIME) ^ (len as u64)
+}
+
+/// 在 `HashMap` 之上叠加一层诊断能力：
+/// - 维护一份有限长度的操作日志，便于事后定位问题
+/// - 给每个桶关联一个 canary（由桶下标和长度推导），每次访问前校验、修改后刷新，
+///   发现长度对不上（说明该桶的条目数跟上次记录的不一致）时带着日志一起
+///   panic——这是一个长度一致性检查，不能发现桶内已有条目被原地改写而
+///   长度不变的破坏
+/// - 提供 [`DiagnosticHashMap::read_only`] 守卫，用来在只读遍历期间
+///   检测表的桶长度是否发生了变化
+pub struct DiagnosticHashMap<K, V> {
+    inner: HashMap<K, V>,
+    /// 用 `RefCell` 包装：`get` 只有 `&self`，但 `HashMap` 的线性哈希分裂
+    /// 随时可能在一次插入后冒出新桶，`get` 也得能把 canary 补齐到位，
+    /// 而不是假设它总跟在 `insert`/`remove` 后面被同步过。
+    canaries: RefCell<Vec<u64>>,
+    journal: RefCell<Journal>,
+}
+
+impl<K, V> DiagnosticHashMap<K, V>
+where
+    K: Hash + Eq,
+{
+    pub fn new() -> Self {
+        Self {
+            inner: HashMap::new(),
+            canaries: RefCell::new(Vec::new()),
+            journal: RefCell::new(Journal::new()),
+        }
+    }
+
+    /// 桶的数量只增不减，新出现的桶在这里补上初始 canary
+    fn sync_canaries(&self) {
+        let mut canaries = self.canaries.borrow_mut();
+        while canaries.len() < self.inner.bucket_count() {
+            let index = canaries.len();
+            canaries.push(canary_for(index, self.inner.bucket_len(index)));
+        }
+    }
+
+    /// 校验前先补齐 canary 数组，这样即便 `index` 是分裂刚刚才生出的
+    /// 新桶，也不会因为 `canaries.len()` 落后于 `bucket_count()` 而越界。
+    fn check_canary(&self, index: usize) {
+        self.sync_canaries();
+        let expected = self.canaries.borrow()[index];
+        let actual = canary_for(index, self.inner.bucket_len(index));
+        if expected != actual {
+            panic!(
+                "DiagnosticHashMap: canary corrupted at bucket {}, recent ops: {:?}",
+                index,
+                self.journal.borrow()
+            );
+        }
+    }
+
+    fn refresh_canary(&self, index: usize) {
+        self.canaries.borrow_mut()[index] = canary_for(index, self.inner.bucket_len(index));
+    }
+
+    /// 一次分裂会改动旧桶（长度变短）和新桶（刚出现），两个都要重新核对，
+    /// 否则任何一个留着分裂前的 canary 都会被当成“被破坏”误报。
+    fn refresh_split(&mut self) {
+        if let Some((old_index, new_index)) = self.inner.take_last_split() {
+            self.sync_canaries();
+            self.refresh_canary(old_index);
+            self.refresh_canary(new_index);
+        }
+    }
+
+    /// 插入键值对，语义与 [`HashMap::insert`] 相同，但会校验/刷新目标桶的 canary
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let index = self.inner.bucket_index_for(&key);
+        self.check_canary(index);
+        let old = self.inner.insert(key, value);
+        self.refresh_canary(index);
+        self.refresh_split();
+        self.journal.borrow_mut().record(Op::Insert, index);
+        old
+    }
+
+    /// 删除键对应的条目，语义与 [`HashMap::remove`] 相同，但会校验/刷新目标桶的 canary
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.inner.bucket_index_for(key);
+        self.check_canary(index);
+        let old = self.inner.remove(key);
+        self.refresh_canary(index);
+        self.refresh_split();
+        self.journal.borrow_mut().record(Op::Remove, index);
+        old
+    }
+
+    /// 获取键对应的值，语义与 [`HashMap::get`] 相同，但会校验目标桶的 canary
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = self.inner.bucket_index_for(key);
+        self.check_canary(index);
+        self.journal.borrow_mut().record(Op::Get, index);
+        self.inner.get(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// 获取一个只读守卫：守卫存续期间借用检查器已经禁止任何 `&mut self`
+    /// 调用，所以它要防的不是安全代码里的误改，而是守卫持有期间表被
+    /// `unsafe` 代码或外部内存破坏打中——析构时重新核对一遍快照时的
+    /// canary，一旦桶长度对不上就带着操作日志 panic。
+    pub fn read_only(&self) -> ReadOnlyGuard<'_, K, V> {
+        self.sync_canaries();
+        ReadOnlyGuard {
+            map: self,
+            snapshot: self.canaries.borrow().clone(),
+        }
+    }
+}
+
+impl<K, V> Default for DiagnosticHashMap<K, V>
+where
+    K: Hash + Eq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 由 [`DiagnosticHashMap::read_only`] 返回的守卫
+pub struct ReadOnlyGuard<'a, K, V>
+where
+    K: Hash + Eq,
+{
+    map: &'a DiagnosticHashMap<K, V>,
+    snapshot: Vec<u64>,
+}
+
+impl<'a, K, V> Drop for ReadOnlyGuard<'a, K, V>
+where
+    K: Hash + Eq,
+{
+    fn drop(&mut self) {
+        for (index, expected) in self.snapshot.iter().enumerate() {
+            let actual = canary_for(index, self.map.inner.bucket_len(index));
+            if *expected != actual {
+                panic!(
+                    "DiagnosticHashMap: bucket {} was mutated while a read_only() guard was held, recent ops: {:?}",
+                    index,
+                    self.map.journal.borrow()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 插入 13 个连续整数 key（足以触发一次分裂），把它们读回来，再 `get`
+    /// 一个落在新桶（下标 16）里的 key 21：这条路径此前会因为 `canaries`
+    /// 没有跟上 `bucket_count()` 而在 `check_canary` 里越界 panic。
+    #[test]
+    fn get_after_split_does_not_panic_on_new_bucket() {
+        let mut map = DiagnosticHashMap::new();
+        for i in 0..13 {
+            map.insert(i, i);
+        }
+        assert!(map.inner.bucket_count() > 16, "13 个 key 应当已经触发一次分裂");
+        for i in 0..13 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+        assert_eq!(map.inner.bucket_index_for(&21), 16);
+        assert_eq!(map.get(&21), None);
+    }
+
+    /// 插入 40 个连续整数 key（触发多轮分裂），然后把它们全部读回来：
+    /// 此前旧桶分裂后留下的 canary 是分裂前的长度，会被误判为“已损坏”。
+    #[test]
+    fn read_back_after_multiple_splits_does_not_panic() {
+        let mut map = DiagnosticHashMap::new();
+        for i in 0..40 {
+            map.insert(i, i * 2);
+        }
+        for i in 0..40 {
+            assert_eq!(map.get(&i), Some(&(i * 2)));
+        }
+    }
+
+    /// `read_only()` 守卫在只读遍历期间不应误报：只要期间没有桶改变长度，
+    /// 析构时的复查应当安静地通过。
+    #[test]
+    fn read_only_guard_does_not_panic_without_mutation() {
+        let mut map = DiagnosticHashMap::new();
+        for i in 0..5 {
+            map.insert(i, i);
+        }
+        let guard = map.read_only();
+        for i in 0..5 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+        drop(guard);
+    }
+}
@@ -3,12 +3,12 @@ use core::hash::BuildHasher;
 use core::hash::{Hash, Hasher};
 
 const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
-const FNV_PRIME: u64 = 0x100000001b3;
-struct FnvHasher {
+pub(crate) const FNV_PRIME: u64 = 0x100000001b3;
+pub(crate) struct FnvHasher {
     hash: u64,
 }
 impl FnvHasher {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         FnvHasher {
             hash: FNV_OFFSET_BASIS,
         }
@@ -28,18 +28,33 @@ impl Hasher for FnvHasher {
 
 // 用于构建 FnvHasher 的 Builder
 #[derive(Default)]
-struct FnvBuildHasher;
+pub(crate) struct FnvBuildHasher;
 impl BuildHasher for FnvBuildHasher {
     type Hasher = FnvHasher;
     fn build_hasher(&self) -> Self::Hasher {
         FnvHasher::new()
     }
 }
+
+/// 触发一次分裂时允许的最大负载因子（len / buckets.len()）
+const LOAD_FACTOR_THRESHOLD: f64 = 0.75;
+
 // HashMap 实现
+//
+// 使用线性哈希（linear hashing）做增量扩容：每次插入最多只分裂一个桶，
+// 而不是像传统实现那样在负载因子超限时一次性 rehash 全表。
+// `n` 是初始桶数，`level` 记录已经完整分裂过多少轮，`split` 指向本轮
+// 下一个待分裂的桶下标。桶的总数恒为 `(n << level) + split`。
 pub struct HashMap<K, V> {
     buckets: Vec<Vec<(K, V)>>,
     build_hasher: FnvBuildHasher,
     len: usize, // 记录元素数量
+    n: usize,
+    level: usize,
+    split: usize,
+    /// 最近一次 `split_bucket` 涉及的 `(旧桶下标, 新桶下标)`，供诊断包装器
+    /// 精确地知道哪些桶需要重新核对，而不必假设全表都可能变过。
+    last_split: Option<(usize, usize)>,
 }
 
 impl<K, V> HashMap<K, V>{
@@ -50,6 +65,10 @@ impl<K, V> HashMap<K, V>{
             buckets: (0..INITIAL_CAPACITY).map(|_| Vec::new()).collect(),
             build_hasher: FnvBuildHasher::default(),
             len: 0,
+            n: INITIAL_CAPACITY,
+            level: 0,
+            split: 0,
+            last_split: None,
         }
     }
 
@@ -79,16 +98,56 @@ impl<K, V> HashMap<K, V>
 where
     K: Hash + Eq, // 要求 Key 可哈希且可比较
 {
-    
+    /// 计算 key 的哈希值
+    fn hash_of(&self, key: &K) -> u64 {
+        let mut hasher = self.build_hasher.build_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// 根据哈希值定位桶下标：
+    /// 先用当前轮次的模数 `n << level` 计算，如果落在已经分裂过的
+    /// 范围（`idx < split`）内，说明该桶已经用更宽的模数 `n << (level + 1)`
+    /// 重新分布过，需要改用更宽的模数重新计算。
+    fn bucket_index(&self, hash: u64) -> usize {
+        let modulus = (self.n << self.level) as u64;
+        let idx = (hash % modulus) as usize;
+        if idx < self.split {
+            let wider_modulus = (self.n << (self.level + 1)) as u64;
+            (hash % wider_modulus) as usize
+        } else {
+            idx
+        }
+    }
+
+    /// 供 crate 内部的诊断包装器使用：返回某个 key 当前会落在哪个桶
+    pub(crate) fn bucket_index_for(&self, key: &K) -> usize {
+        let hash = self.hash_of(key);
+        self.bucket_index(hash)
+    }
+
+    /// 供 crate 内部的诊断包装器使用：当前的桶总数
+    pub(crate) fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// 供 crate 内部的诊断包装器使用：某个桶当前的条目数
+    pub(crate) fn bucket_len(&self, index: usize) -> usize {
+        self.buckets[index].len()
+    }
+
+    /// 供 crate 内部的诊断包装器使用：取走并清空“最近一次分裂涉及的桶”记录
+    pub(crate) fn take_last_split(&mut self) -> Option<(usize, usize)> {
+        self.last_split.take()
+    }
+
     /// 插入键值对，如果键已存在则替换旧值，并返回旧值  成功返回 None
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
         // 1. 计算hash
-        let mut hasher = self.build_hasher.build_hasher();
-        key.hash(&mut hasher);
-        let hash = hasher.finish();
+        let hash = self.hash_of(&key);
 
         // 2. 计算索引
-        let index = (hash % self.buckets.len() as u64) as usize;
+        let index = self.bucket_index(hash);
         let bucket = &mut self.buckets[index];
 
         // 3. 查询如果存在则更新value，否则插入
@@ -100,17 +159,14 @@ where
         bucket.push((key, value));
         self.len += 1;
 
+        self.maybe_split();
+
         None
     }
     /// 获取键对应的值
     pub fn get(&self, key: &K) -> Option<&V> {
-        // 1. 计算hash
-        let mut hasher = self.build_hasher.build_hasher();
-        key.hash(&mut hasher);
-        let hash = hasher.finish();
-
-        // 2. 计算索引
-        let index = (hash % self.buckets.len() as u64) as usize;
+        let hash = self.hash_of(key);
+        let index = self.bucket_index(hash);
         self.buckets[index]
             .iter()
             .find(|(k, _)| k == key)
@@ -118,20 +174,182 @@ where
     }
     /// 获取键对应的可变值
     pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
-        // 1. 计算hash
-        let mut hasher = self.build_hasher.build_hasher();
-        key.hash(&mut hasher);
-        let hash = hasher.finish();
-
-        // 2. 计算索引
-        let index = (hash % self.buckets.len() as u64) as usize;
+        let hash = self.hash_of(key);
+        let index = self.bucket_index(hash);
         self.buckets[index]
             .iter_mut()
             .find(|(k, _)| k == key)
             .map(|(_, v)| v)
     }
-    
+
+    /// 删除键对应的条目，返回被删除的值；键不存在时返回 None。
+    /// 用 `swap_remove` 避免在桶内搬移剩余元素。
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let hash = self.hash_of(key);
+        let index = self.bucket_index(hash);
+        let bucket = &mut self.buckets[index];
+        let pos = bucket.iter().position(|(k, _)| k == key)?;
+        let (_, value) = bucket.swap_remove(pos);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// 获取键对应的 Entry，只做一次哈希与探测，供调用方在
+    /// “不存在则插入，存在则修改” 的场景下避免重复查找。
+    ///
+    /// `Entry` 持有 `&mut HashMap` 而不是直接借走目标桶，这样
+    /// `VacantEntry::insert` 才能在插入后照常调用 `maybe_split`，
+    /// 不绕过线性哈希的增量扩容。
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let hash = self.hash_of(&key);
+        let index = self.bucket_index(hash);
+        let pos = self.buckets[index].iter().position(|(k, _)| *k == key);
+        match pos {
+            Some(pos) => Entry::Occupied(OccupiedEntry {
+                map: self,
+                index,
+                pos,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                map: self,
+                index,
+                hash,
+                key,
+            }),
+        }
+    }
+
+    /// 若负载因子超过阈值，分裂 `split` 指向的那一个桶，把扩容成本
+    /// 均摊到每一次插入上，避免一次性 rehash 整张表。
+    fn maybe_split(&mut self) {
+        if self.len as f64 / self.buckets.len() as f64 <= LOAD_FACTOR_THRESHOLD {
+            return;
+        }
+        self.split_bucket();
+    }
+
+    /// 分裂 `split` 指向的桶：新开一个桶，用更宽的模数把旧桶中的条目
+    /// 重新分布到旧下标和新下标之间，然后推进 `split`/`level`。
+    fn split_bucket(&mut self) {
+        let old_index = self.split;
+        let new_index = old_index + (self.n << self.level);
+        let wider_modulus = (self.n << (self.level + 1)) as u64;
+
+        self.buckets.push(Vec::new());
+
+        let old_bucket = core::mem::take(&mut self.buckets[old_index]);
+        let mut kept = Vec::new();
+        let mut moved = Vec::new();
+        for entry in old_bucket {
+            let hash = self.hash_of(&entry.0);
+            if (hash % wider_modulus) as usize == new_index {
+                moved.push(entry);
+            } else {
+                kept.push(entry);
+            }
+        }
+        self.buckets[old_index] = kept;
+        self.buckets[new_index] = moved;
+        self.last_split = Some((old_index, new_index));
+
+        self.split += 1;
+        if self.split == self.n << self.level {
+            self.level += 1;
+            self.split = 0;
+        }
+    }
+}
+
+/// 对 `HashMap` 中一个键位置的视图，由 [`HashMap::entry`] 返回。
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/// 指向已存在条目所在桶和下标的视图
+pub struct OccupiedEntry<'a, K, V> {
+    map: &'a mut HashMap<K, V>,
+    index: usize,
+    pos: usize,
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    /// 返回条目当前的值
+    pub fn get(&self) -> &V {
+        &self.map.buckets[self.index][self.pos].1
+    }
+    /// 返回条目当前值的可变引用
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.buckets[self.index][self.pos].1
+    }
+    /// 消费该视图，返回与 `HashMap` 同生命周期的可变引用
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.buckets[self.index][self.pos].1
+    }
 }
+
+/// 指向键不存在时应插入位置的视图
+pub struct VacantEntry<'a, K, V> {
+    map: &'a mut HashMap<K, V>,
+    index: usize,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V>
+where
+    K: Hash + Eq,
+{
+    /// 将值插入这个空位，返回插入值的可变引用。
+    ///
+    /// 插入后照常调用 `maybe_split`：如果它刚好分裂了这个桶，条目可能被
+    /// 搬到了新桶里，所以用当初算好的 `hash` 重新定位桶下标，而不是沿用
+    /// 插入前的 `index`。
+    pub fn insert(self, value: V) -> &'a mut V {
+        let VacantEntry {
+            map,
+            index,
+            hash,
+            key,
+        } = self;
+        map.buckets[index].push((key, value));
+        map.len += 1;
+        map.maybe_split();
+
+        let index = map.bucket_index(hash);
+        &mut map.buckets[index].last_mut().unwrap().1
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    K: Hash + Eq,
+{
+    /// 键存在则返回其值的引用，否则插入 `default` 并返回新值的引用
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// 与 [`Entry::or_insert`] 类似，但惰性计算默认值
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// 若条目已存在则对其值执行 `f`，再返回该 Entry 以便继续链式调用
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(ref mut entry) = self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
 // 为 HashMap 实现 Default
 impl<K, V> Default for HashMap<K, V>
 {
@@ -139,3 +357,57 @@ impl<K, V> Default for HashMap<K, V>
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 连续插入足够多的 key 以触发多轮分裂，期间每插入一个就立刻用
+    /// `get` 核对所有已插入的 key 都能查到、且没有串桶；随后 `remove` 掉
+    /// 所有偶数 key，再核对剩余 key 仍然可查、已删除的 key 确实消失。
+    #[test]
+    fn split_preserves_get_and_remove() {
+        let mut map = HashMap::new();
+        let n: i32 = 200;
+
+        for i in 0..n {
+            map.insert(i, i * 10);
+            for j in 0..=i {
+                assert_eq!(map.get(&j), Some(&(j * 10)));
+            }
+        }
+        assert!(map.bucket_count() > 16, "多轮插入后应当已经分裂扩容");
+        assert_eq!(map.len(), n as usize);
+
+        for i in 0..n {
+            if i % 2 == 0 {
+                assert_eq!(map.remove(&i), Some(i * 10));
+            }
+        }
+        for i in 0..n {
+            if i % 2 == 0 {
+                assert_eq!(map.get(&i), None);
+            } else {
+                assert_eq!(map.get(&i), Some(&(i * 10)));
+            }
+        }
+    }
+
+    /// 回归测试：只通过 `entry(...).or_insert(...)` 插入也必须触发
+    /// `maybe_split`，不能让线性哈希的增量扩容被这条路径绕过。
+    #[test]
+    fn entry_or_insert_triggers_split() {
+        let mut map = HashMap::new();
+        for i in 0..1000 {
+            *map.entry(i).or_insert(0) += 1;
+        }
+        assert_eq!(map.len(), 1000);
+        assert!(
+            map.bucket_count() > 16,
+            "纯 entry().or_insert() 插入也应当像 insert 一样触发分裂"
+        );
+        for i in 0..1000 {
+            assert_eq!(map.get(&i), Some(&1));
+        }
+    }
+}
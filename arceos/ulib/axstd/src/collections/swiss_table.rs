@@ -0,0 +1,300 @@
+//! `SwissTable`：[`super::hashmap::HashMap`] 的开放寻址替代实现。
+//!
+//! 链式 `HashMap` 每个桶都是一个独立的 `Vec`，指针追逐多、缓存行为差。
+//! `SwissTable` 改为一段连续的 `(K, V)` 槽位数组，外加一份并行的 control
+//! byte 数组：每个 control byte 要么是 `EMPTY`/`DELETED`，要么保存哈希低 7
+//! 位的 tag。按 [`GROUP_SIZE`] 个 control byte 为一组分组探测：先用
+//! `h1 = hash >> 7` 定位起始分组，组内逐字节比较 tag（`h2 = hash & 0x7f`）
+//! 筛出候选槽位再做真正的 key 比较；一组内没有命中也没有空位，就探测下一组。
+//! 当负载因子（算上墓碑位）超过 7/8 时整表翻倍扩容。
+
+use super::hashmap::{FnvBuildHasher, FnvHasher};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash, Hasher};
+
+/// 一次分组探测覆盖的 control byte 数量
+const GROUP_SIZE: usize = 16;
+
+/// 空槽位
+const EMPTY: u8 = 0xFF;
+/// 曾经被删除、但仍可能挡在某次探测路径上的槽位（墓碑）
+const DELETED: u8 = 0x80;
+
+fn h1(hash: u64) -> usize {
+    (hash >> 7) as usize
+}
+
+fn h2(hash: u64) -> u8 {
+    (hash & 0x7f) as u8
+}
+
+fn is_full(ctrl: u8) -> bool {
+    ctrl & 0x80 == 0
+}
+
+pub struct SwissTable<K, V> {
+    /// 与 `slots` 等长，每个元素要么是 `EMPTY`/`DELETED`，要么是 7 位 tag
+    ctrl: Vec<u8>,
+    slots: Vec<Option<(K, V)>>,
+    build_hasher: FnvBuildHasher,
+    len: usize,
+    tombstones: usize,
+}
+
+impl<K, V> SwissTable<K, V> {
+    /// 创建一个新的空 SwissTable
+    pub fn new() -> Self {
+        const INITIAL_CAPACITY: usize = GROUP_SIZE;
+        Self {
+            ctrl: vec![EMPTY; INITIAL_CAPACITY],
+            slots: (0..INITIAL_CAPACITY).map(|_| None).collect(),
+            build_hasher: FnvBuildHasher::default(),
+            len: 0,
+            tombstones: 0,
+        }
+    }
+
+    /// 返回元素数量
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 判断是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn capacity(&self) -> usize {
+        self.ctrl.len()
+    }
+
+    /// 创建一个迭代器
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.ctrl
+            .iter()
+            .zip(self.slots.iter())
+            .filter(|(&ctrl, _)| is_full(ctrl))
+            .filter_map(|(_, slot)| slot.as_ref().map(|(k, v)| (k, v)))
+    }
+}
+
+impl<K, V> SwissTable<K, V>
+where
+    K: Hash + Eq,
+{
+    fn hash_of(&self, key: &K) -> u64 {
+        let mut hasher: FnvHasher = self.build_hasher.build_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// 插入键值对，如果键已存在则替换旧值，并返回旧值，成功返回 None
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if (self.len + self.tombstones + 1) * 8 > self.capacity() * 7 {
+            self.grow();
+        }
+
+        let hash = self.hash_of(&key);
+        let tag = h2(hash);
+        let group_start = h1(hash) % self.capacity();
+        let mut first_tombstone = None;
+        let mut group = group_start;
+
+        loop {
+            for offset in 0..GROUP_SIZE {
+                let idx = (group + offset) % self.capacity();
+                let ctrl = self.ctrl[idx];
+                if ctrl == tag {
+                    if let Some((k, _)) = &self.slots[idx] {
+                        if *k == key {
+                            let (_, old) = self.slots[idx].take().unwrap();
+                            self.slots[idx] = Some((key, value));
+                            return Some(old);
+                        }
+                    }
+                } else if ctrl == EMPTY {
+                    let target = first_tombstone.unwrap_or(idx);
+                    if first_tombstone.is_some() {
+                        self.tombstones -= 1;
+                    }
+                    self.ctrl[target] = tag;
+                    self.slots[target] = Some((key, value));
+                    self.len += 1;
+                    return None;
+                } else if ctrl == DELETED && first_tombstone.is_none() {
+                    first_tombstone = Some(idx);
+                }
+            }
+            group = (group + GROUP_SIZE) % self.capacity();
+            if group == group_start {
+                // 扩容时已经保证负载因子留有余量，这里只会在墓碑恰好
+                // 排满所有分组时触发，直接复用找到的第一个墓碑
+                let target = first_tombstone.expect("SwissTable: table full despite load-factor check");
+                self.tombstones -= 1;
+                self.ctrl[target] = tag;
+                self.slots[target] = Some((key, value));
+                self.len += 1;
+                return None;
+            }
+        }
+    }
+
+    /// 获取键对应的值
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = self.find(key)?;
+        self.slots[index].as_ref().map(|(_, v)| v)
+    }
+
+    /// 获取键对应的可变值
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.find(key)?;
+        self.slots[index].as_mut().map(|(_, v)| v)
+    }
+
+    /// 删除键对应的条目，返回被删除的值；键不存在时返回 None。
+    /// 删除后把该槽位标记为 `DELETED`（墓碑），避免打断其它键原本经过
+    /// 这个槽位的探测路径。
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.find(key)?;
+        self.ctrl[index] = DELETED;
+        self.tombstones += 1;
+        self.len -= 1;
+        self.slots[index].take().map(|(_, v)| v)
+    }
+
+    /// 按分组探测定位键所在槽位的下标
+    fn find(&self, key: &K) -> Option<usize> {
+        let hash = self.hash_of(key);
+        let tag = h2(hash);
+        let group_start = h1(hash) % self.capacity();
+        let mut group = group_start;
+
+        loop {
+            let mut saw_empty = false;
+            for offset in 0..GROUP_SIZE {
+                let idx = (group + offset) % self.capacity();
+                let ctrl = self.ctrl[idx];
+                if ctrl == tag {
+                    if let Some((k, _)) = &self.slots[idx] {
+                        if k == key {
+                            return Some(idx);
+                        }
+                    }
+                } else if ctrl == EMPTY {
+                    saw_empty = true;
+                }
+            }
+            // 一组内出现空槽位，说明探测链到此为止，键不存在
+            if saw_empty {
+                return None;
+            }
+            group = (group + GROUP_SIZE) % self.capacity();
+            if group == group_start {
+                return None;
+            }
+        }
+    }
+
+    /// 表翻倍扩容：清空 control byte 数组，把旧槽位里的条目逐个重新插入
+    fn grow(&mut self) {
+        let new_capacity = self.capacity() * 2;
+        let old_slots = core::mem::replace(
+            &mut self.slots,
+            (0..new_capacity).map(|_| None).collect(),
+        );
+        self.ctrl = vec![EMPTY; new_capacity];
+        self.len = 0;
+        self.tombstones = 0;
+
+        for (key, value) in old_slots.into_iter().flatten() {
+            self.insert_no_grow(key, value);
+        }
+    }
+
+    /// 在已确定容量足够、且键两两不同的前提下插入（仅供 `grow` 重新哈希使用）
+    fn insert_no_grow(&mut self, key: K, value: V) {
+        let hash = self.hash_of(&key);
+        let tag = h2(hash);
+        let group_start = h1(hash) % self.capacity();
+        let mut group = group_start;
+
+        loop {
+            for offset in 0..GROUP_SIZE {
+                let idx = (group + offset) % self.capacity();
+                if self.ctrl[idx] == EMPTY {
+                    self.ctrl[idx] = tag;
+                    self.slots[idx] = Some((key, value));
+                    self.len += 1;
+                    return;
+                }
+            }
+            group = (group + GROUP_SIZE) % self.capacity();
+        }
+    }
+}
+
+impl<K, V> Default for SwissTable<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_get_update_remove_round_trip() {
+        let mut table = SwissTable::new();
+        assert_eq!(table.insert(1, "a"), None);
+        assert_eq!(table.insert(2, "b"), None);
+        assert_eq!(table.get(&1), Some(&"a"));
+        assert_eq!(table.get(&2), Some(&"b"));
+        assert_eq!(table.get(&3), None);
+
+        assert_eq!(table.insert(1, "a2"), Some("a"));
+        assert_eq!(table.get(&1), Some(&"a2"));
+
+        assert_eq!(table.remove(&1), Some("a2"));
+        assert_eq!(table.get(&1), None);
+        assert_eq!(table.remove(&1), None);
+        assert_eq!(table.len(), 1);
+    }
+
+    /// 删除后留下的墓碑不应打断其它键原本经过该槽位的探测路径。
+    #[test]
+    fn tombstone_does_not_break_further_probes() {
+        let mut table = SwissTable::new();
+        for i in 0..8 {
+            table.insert(i, i * 10);
+        }
+        // 删掉一半，制造墓碑，但不触发扩容
+        for i in (0..8).step_by(2) {
+            assert_eq!(table.remove(&i), Some(i * 10));
+        }
+        for i in 0..8 {
+            if i % 2 == 0 {
+                assert_eq!(table.get(&i), None);
+            } else {
+                assert_eq!(table.get(&i), Some(&(i * 10)));
+            }
+        }
+        // 墓碑槽位应当能被新键复用
+        assert_eq!(table.insert(100, 1000), None);
+        assert_eq!(table.get(&100), Some(&1000));
+    }
+
+    /// 插入足够多的键触发多轮扩容（负载因子 7/8），所有条目都应当保留。
+    #[test]
+    fn grow_preserves_all_entries() {
+        let mut table = SwissTable::new();
+        for i in 0..200 {
+            assert_eq!(table.insert(i, i * 2), None);
+        }
+        assert_eq!(table.len(), 200);
+        for i in 0..200 {
+            assert_eq!(table.get(&i), Some(&(i * 2)));
+        }
+    }
+}